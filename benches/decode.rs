@@ -0,0 +1,176 @@
+// Copyright (c) "Neo4j"
+// Neo4j Sweden AB [https://neo4j.com]
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks `read_value`'s marker dispatch against representative Bolt
+//! record streams (lists of maps of mixed scalars, nested structs) and
+//! against values chosen to sit right at `write_int`'s width-selection
+//! boundaries, so an encoding-width regression shows up as a throughput
+//! change here. Run with `cargo bench --features bench`.
+//!
+//! The structured property test living alongside this is
+//! `v1::tests::unpack_of_pack_is_identity_for_generated_values`, which
+//! asserts `unpack(pack(x)) == x` for generated value trees across every
+//! supported type and runs under plain `cargo test`; `fuzz/fuzz_targets/
+//! roundtrip.rs` shares the same generator but explores far more of the
+//! input space under `cargo fuzz run`. Neither replaces a fixed benchmark
+//! corpus -- a regression here is a throughput change, not a failure.
+//!
+//! This snapshot has no root `Cargo.toml`; see the comment at the top of
+//! `fuzz/Cargo.toml` for the manifest entries this bench target still
+//! needs (the `bench` feature, `criterion` dev-dependency, and a
+//! `[[bench]]` entry) before `cargo bench --features bench` can run it.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use pyo3::types::PyByteArray;
+use pyo3::{IntoPy, Python};
+
+use neo4j_rust_ext::v1::{pack, unpack, DEFAULT_MAX_DEPTH};
+
+/// A `Record`-shaped struct (tag 0x71) wrapping a list of fields: a
+/// `Node`-shaped struct (tag 0x4E) whose properties map holds
+/// string/int/float scalars, an int and a string -- the PackStream
+/// shape the driver decodes most often when streaming query results.
+fn build_record_stream(py: Python<'_>, num_records: usize) -> Vec<u8> {
+    let locals = pyo3::types::PyDict::new(py);
+    locals.set_item("num_records", num_records).unwrap();
+    py.run(
+        r#"
+from neo4j._codec.packstream._rust import Structure, v1
+
+records = []
+for i in range(num_records):
+    node = Structure(
+        b"N",
+        i,
+        ["Person", "Employee"],
+        {"name": f"user-{i}", "age": 20 + (i % 50), "score": i * 1.5},
+    )
+    records.append(Structure(b"\x71", [node, i, f"note-{i}"]))
+
+buf = bytearray()
+for record in records:
+    buf += v1.pack(record, None)
+"#,
+        None,
+        Some(locals),
+    )
+    .expect("benchmark fixture setup must not fail");
+    locals
+        .get_item("buf")
+        .unwrap()
+        .extract::<Vec<u8>>()
+        .unwrap()
+}
+
+fn bench_decode_record_stream(c: &mut Criterion) {
+    Python::with_gil(|py| {
+        let mut group = c.benchmark_group("decode_record_stream");
+        for num_records in [1, 16, 256] {
+            let data = build_record_stream(py, num_records);
+            group.bench_with_input(
+                BenchmarkId::from_parameter(num_records),
+                &data,
+                |b, data| {
+                    b.iter(|| {
+                        let buffer = PyByteArray::new(py, data);
+                        let mut idx = 0;
+                        while idx < data.len() {
+                            let (_, new_idx) = unpack(py, buffer, idx, None, DEFAULT_MAX_DEPTH).unwrap();
+                            idx = new_idx;
+                        }
+                    });
+                },
+            );
+        }
+        group.finish();
+    });
+}
+
+fn bench_pack(c: &mut Criterion) {
+    Python::with_gil(|py| {
+        let locals = pyo3::types::PyDict::new(py);
+        py.run(
+            r#"
+value = {
+    "id": 42,
+    "labels": ["Person", "Employee"],
+    "properties": {"name": "user-42", "age": 33, "score": 63.0},
+}
+"#,
+            None,
+            Some(locals),
+        )
+        .unwrap();
+        let value = locals.get_item("value").unwrap();
+        c.bench_function("pack_node_like_map", |b| {
+            b.iter(|| pack(py, value, None).unwrap());
+        });
+    });
+}
+
+/// One value at each integer-width transition (`-16..127` tiny-int range,
+/// then the `INT_8`/`INT_16`/`INT_32`/`INT_64` boundaries on both sides),
+/// packed together so a regression in `write_int`'s width selection or
+/// `read_value`'s marker table shows up as a throughput change here
+/// rather than only as a silent correctness bug.
+fn int_boundary_values() -> Vec<i64> {
+    vec![
+        -16,
+        127,
+        -17,
+        -128,
+        128,
+        32_767,
+        -32_768,
+        -32_769,
+        2_147_483_647,
+        -2_147_483_648,
+        -2_147_483_649,
+        i64::MAX,
+        i64::MIN,
+    ]
+}
+
+fn bench_int_boundaries(c: &mut Criterion) {
+    Python::with_gil(|py| {
+        let packed: Vec<u8> = int_boundary_values()
+            .into_iter()
+            .flat_map(|v| {
+                pack(py, v.into_py(py).as_ref(py), None)
+                    .unwrap()
+                    .as_bytes()
+                    .to_vec()
+            })
+            .collect();
+        c.bench_function("roundtrip_int_width_boundaries", |b| {
+            b.iter(|| {
+                let buffer = PyByteArray::new(py, &packed);
+                let mut idx = 0;
+                while idx < packed.len() {
+                    let (_, new_idx) = unpack(py, buffer, idx, None, DEFAULT_MAX_DEPTH).unwrap();
+                    idx = new_idx;
+                }
+            });
+        });
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_decode_record_stream,
+    bench_pack,
+    bench_int_boundaries
+);
+criterion_main!(benches);
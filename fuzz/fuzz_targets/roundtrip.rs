@@ -0,0 +1,41 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pyo3::types::PyByteArray;
+use pyo3::Python;
+
+use neo4j_rust_ext::v1::fuzzing::PackStreamValue;
+use neo4j_rust_ext::v1::{pack, unpack, DEFAULT_MAX_DEPTH};
+
+// Structured round-trip fuzzing: generate a `PackStreamValue` tree, pack
+// it, unpack it, and assert the result is equal to what went in. Any
+// mismatch (or panic/UB surfaced via `unsafe` slicing in the decoder)
+// means `pack`/`unpack` have drifted apart for some marker/length/UTF-8
+// edge case that the hand-written test suite doesn't cover.
+fuzz_target!(|value: PackStreamValue| {
+    Python::with_gil(|py| {
+        let original = value.to_object(py);
+        let packed = match pack(py, original.as_ref(py), None) {
+            Ok(bytes) => bytes,
+            // Not expected: `PackStreamValue` stays within every header
+            // size limit (`MAX_FANOUT` caps structs at 4 fields), so a
+            // pack error is skipped rather than compared.
+            Err(_) => return,
+        };
+        let buffer = PyByteArray::new(py, packed.as_bytes());
+        let (decoded, consumed) = unpack(py, buffer, 0, None, DEFAULT_MAX_DEPTH)
+            .expect("pack's own output must always be decodable by unpack");
+        assert_eq!(
+            consumed,
+            packed.as_bytes().len(),
+            "unpack must consume exactly the bytes pack produced"
+        );
+        assert!(
+            original
+                .as_ref(py)
+                .eq(decoded.as_ref(py))
+                .expect("comparable values"),
+            "unpack(pack(value)) != value"
+        );
+    });
+});
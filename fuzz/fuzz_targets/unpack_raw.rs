@@ -0,0 +1,24 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pyo3::types::PyByteArray;
+use pyo3::Python;
+
+use neo4j_rust_ext::v1::{unpack, DEFAULT_MAX_DEPTH};
+
+// Feed raw arbitrary bytes straight into `unpack` (no structure imposed by
+// `PackStreamValue`) to confirm malformed input is always rejected with a
+// `PyValueError` rather than panicking or tripping UB in the `unsafe`
+// `as_bytes()` slices used by the string/bytes readers.
+//
+// Any crashers found this way should be turned into permanent regression
+// cases under `fuzz/corpus/unpack_raw/regressions/` rather than just
+// living in a comment here.
+fuzz_target!(|bytes: &[u8]| {
+    Python::with_gil(|py| {
+        let buffer = PyByteArray::new(py, bytes);
+        match unpack(py, buffer, 0, None, DEFAULT_MAX_DEPTH) {
+            Ok(_) | Err(_) => {}
+        }
+    });
+});
@@ -16,6 +16,18 @@
 mod pack;
 mod unpack;
 
+// Also built under `cfg(test)` so the round-trip property test in this
+// module's own test suite runs as part of plain `cargo test`, not just
+// `cargo fuzz run`.
+#[cfg(any(test, fuzzing))]
+pub mod fuzzing;
+// Exposed to the fuzz targets under `fuzz/` and the Criterion benchmarks
+// under `benches/`, neither of which are reachable from published builds.
+#[cfg(any(fuzzing, feature = "bench"))]
+pub use pack::pack;
+#[cfg(any(fuzzing, feature = "bench"))]
+pub use unpack::{unpack, DEFAULT_MAX_DEPTH};
+
 use pyo3::prelude::*;
 use pyo3::wrap_pyfunction;
 
@@ -46,7 +58,74 @@ const BYTES_32: u8 = 0xCE;
 
 pub(crate) fn register(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(unpack::unpack, m)?)?;
+    m.add_function(wrap_pyfunction!(unpack::unpack_incremental, m)?)?;
     m.add_function(wrap_pyfunction!(pack::pack, m)?)?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use arbitrary::{Arbitrary, Unstructured};
+    use pyo3::types::PyByteArray;
+    use pyo3::Python;
+
+    use super::fuzzing::PackStreamValue;
+    use super::{pack, unpack};
+
+    /// `unpack(pack(x)) == x` for a generated value tree of every
+    /// supported type, run as part of plain `cargo test` (the
+    /// `arbitrary`-backed generator also backs `fuzz/fuzz_targets/
+    /// roundtrip.rs`, which explores far more of the input space but
+    /// only runs under `cargo fuzz run`). Seeded from a fixed byte
+    /// buffer rather than OS randomness so this is reproducible in CI.
+    ///
+    /// Bounded by `ROUNDS` rather than looping until `Unstructured`
+    /// errors: once the seed buffer is exhausted, `arbitrary`'s
+    /// primitive generators zero-pad and keep returning `Ok` instead of
+    /// erroring, so an unbounded `while let Ok(...)` here would spin
+    /// forever on `PackStreamValue::Null` rather than finishing.
+    #[test]
+    fn unpack_of_pack_is_identity_for_generated_values() {
+        const ROUNDS: usize = 200;
+
+        let seed: Vec<u8> = (0..8192u32).map(|i| (i % 256) as u8).collect();
+        let mut u = Unstructured::new(&seed);
+
+        Python::with_gil(|py| {
+            let mut checked = 0;
+            for _ in 0..ROUNDS {
+                if u.is_empty() {
+                    break;
+                }
+                let value = PackStreamValue::arbitrary(&mut u)
+                    .expect("generation from a non-empty Unstructured cannot fail");
+                let original = value.to_object(py);
+                let packed = match pack::pack(py, original.as_ref(py), None) {
+                    Ok(bytes) => bytes,
+                    // Not expected: generated values stay within every
+                    // header size limit; skipped rather than compared.
+                    Err(_) => continue,
+                };
+                let buffer = PyByteArray::new(py, packed.as_bytes());
+                let (decoded, consumed) =
+                    unpack::unpack(py, buffer, 0, None, unpack::DEFAULT_MAX_DEPTH)
+                        .expect("pack's own output must always be decodable by unpack");
+                assert_eq!(
+                    consumed,
+                    packed.as_bytes().len(),
+                    "unpack must consume exactly the bytes pack produced"
+                );
+                assert!(
+                    original
+                        .as_ref(py)
+                        .eq(decoded.as_ref(py))
+                        .expect("comparable values"),
+                    "unpack(pack(value)) != value"
+                );
+                checked += 1;
+            }
+            assert!(checked > 0, "seed buffer ran out of entropy immediately");
+        });
+    }
+}
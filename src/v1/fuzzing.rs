@@ -0,0 +1,144 @@
+// Copyright (c) "Neo4j"
+// Neo4j Sweden AB [https://neo4j.com]
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Value model shared by the structured fuzz target under `fuzz/` and by
+//! the `unpack_of_pack_is_identity_for_generated_values` test in `v1`'s
+//! own test suite.
+//!
+//! Only built under `cfg(test)` or `cfg(fuzzing)` (`cargo fuzz`); it gives
+//! both a single, reusable definition of "an arbitrary PackStream value"
+//! instead of duplicating generation logic in each one.
+
+use arbitrary::{Arbitrary, Unstructured};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList};
+
+use crate::Structure;
+
+/// Generated values are capped at this nesting depth so that `Unstructured`
+/// can't be coaxed into building a list/map/struct tree deep enough to
+/// blow the stack while we're still generating it (independent of the
+/// decoder's own `max_depth` guard, which is exercised by feeding the
+/// *encoded bytes* of deliberately deep structures instead).
+const MAX_DEPTH: usize = 8;
+
+/// Max number of elements generated for any single list, map, or struct.
+/// Kept small so fuzzing explores shape/nesting rather than spending its
+/// budget on wide, repetitive containers.
+const MAX_FANOUT: usize = 4;
+
+#[derive(Debug, Clone)]
+pub enum PackStreamValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Bytes(Vec<u8>),
+    String(String),
+    List(Vec<PackStreamValue>),
+    Map(Vec<(String, PackStreamValue)>),
+    Struct { tag: u8, fields: Vec<PackStreamValue> },
+}
+
+impl<'a> Arbitrary<'a> for PackStreamValue {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Self::arbitrary_at_depth(u, 0)
+    }
+}
+
+impl PackStreamValue {
+    fn arbitrary_at_depth(u: &mut Unstructured<'_>, depth: usize) -> arbitrary::Result<Self> {
+        if depth >= MAX_DEPTH {
+            return Self::arbitrary_leaf(u);
+        }
+        Ok(match u.int_in_range(0..=8)? {
+            0 => PackStreamValue::Null,
+            1 => PackStreamValue::Bool(bool::arbitrary(u)?),
+            2 => PackStreamValue::Int(i64::arbitrary(u)?),
+            3 => PackStreamValue::Float(f64::arbitrary(u)?),
+            4 => PackStreamValue::Bytes(Vec::arbitrary(u)?),
+            5 => PackStreamValue::String(String::arbitrary(u)?),
+            6 => PackStreamValue::List(Self::arbitrary_vec(u, depth)?),
+            7 => {
+                let len = u.int_in_range(0..=MAX_FANOUT)?;
+                let mut items = Vec::with_capacity(len);
+                for _ in 0..len {
+                    items.push((String::arbitrary(u)?, Self::arbitrary_at_depth(u, depth + 1)?));
+                }
+                PackStreamValue::Map(items)
+            }
+            _ => PackStreamValue::Struct {
+                tag: u8::arbitrary(u)?,
+                fields: Self::arbitrary_vec(u, depth)?,
+            },
+        })
+    }
+
+    fn arbitrary_vec(
+        u: &mut Unstructured<'_>,
+        depth: usize,
+    ) -> arbitrary::Result<Vec<PackStreamValue>> {
+        let len = u.int_in_range(0..=MAX_FANOUT)?;
+        let mut items = Vec::with_capacity(len);
+        for _ in 0..len {
+            items.push(Self::arbitrary_at_depth(u, depth + 1)?);
+        }
+        Ok(items)
+    }
+
+    fn arbitrary_leaf(u: &mut Unstructured<'_>) -> arbitrary::Result<Self> {
+        Ok(match u.int_in_range(0..=5)? {
+            0 => PackStreamValue::Null,
+            1 => PackStreamValue::Bool(bool::arbitrary(u)?),
+            2 => PackStreamValue::Int(i64::arbitrary(u)?),
+            3 => PackStreamValue::Float(f64::arbitrary(u)?),
+            4 => PackStreamValue::Bytes(Vec::arbitrary(u)?),
+            _ => PackStreamValue::String(String::arbitrary(u)?),
+        })
+    }
+
+    /// Materialize this value tree as the Python object the pure-Python
+    /// driver would have produced for the same payload, so it can be
+    /// handed to `pack` exactly like any other caller-supplied value.
+    pub fn to_object(&self, py: Python<'_>) -> PyObject {
+        match self {
+            PackStreamValue::Null => py.None(),
+            PackStreamValue::Bool(b) => b.into_py(py),
+            PackStreamValue::Int(i) => i.into_py(py),
+            PackStreamValue::Float(f) => f.into_py(py),
+            PackStreamValue::Bytes(b) => PyBytes::new(py, b).into_py(py),
+            PackStreamValue::String(s) => s.into_py(py),
+            PackStreamValue::List(items) => PyList::new(
+                py,
+                items.iter().map(|item| item.to_object(py)).collect::<Vec<_>>(),
+            )
+            .into_py(py),
+            PackStreamValue::Map(entries) => {
+                let dict = PyDict::new(py);
+                for (key, value) in entries {
+                    // Arbitrary map keys may collide; last write wins, same
+                    // as constructing a Python dict from a list of pairs.
+                    dict.set_item(key, value.to_object(py)).expect("infallible dict set_item");
+                }
+                dict.into_py(py)
+            }
+            PackStreamValue::Struct { tag, fields } => Structure {
+                tag: *tag,
+                fields: fields.iter().map(|field| field.to_object(py)).collect(),
+            }
+            .into_py(py),
+        }
+    }
+}
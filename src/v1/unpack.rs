@@ -13,6 +13,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+//! The inverse of `pack.rs`: `PackStreamDecoder` decodes every marker
+//! `PackStreamEncoder` can write, including the `BYTES_8`/`BYTES_16`/
+//! `BYTES_32` markers `write_bytes` emits (`read_bytes`), so packing a
+//! `bytes` value and unpacking it again always round-trips.
+//!
+//! Every `read_*` method returns `PyResult` and bounds-checks the slice
+//! it reads before touching it: truncated input raises `PyValueError`
+//! ("Nothing to unpack") rather than indexing out of bounds, and invalid
+//! UTF-8 or an unrecognized marker raise `PyValueError` rather than
+//! panicking. Nothing here should ever abort the interpreter on
+//! attacker-controlled bytes; see `fuzz/fuzz_targets/unpack_raw.rs` for
+//! the harness that checks exactly that.
+
 use pyo3::exceptions::PyValueError;
 use pyo3::intern;
 use pyo3::prelude::*;
@@ -25,23 +38,174 @@ use super::{
 };
 use crate::Structure;
 
+/// What to do with a given marker byte, precomputed once into
+/// [`MARKER_TABLE`] instead of re-deriving it (tiny-int range check,
+/// high-nibble comparisons, ...) on every call to `read_value`.
+#[derive(Clone, Copy)]
+enum MarkerAction {
+    TinyInt(i8),
+    Null,
+    False,
+    True,
+    Float64,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    Bytes8,
+    Bytes16,
+    Bytes32,
+    TinyString(u8),
+    String8,
+    String16,
+    String32,
+    TinyList(u8),
+    List8,
+    List16,
+    List32,
+    TinyMap(u8),
+    Map8,
+    Map16,
+    Map32,
+    TinyStruct(u8),
+    Invalid,
+}
+
+const fn classify_marker(marker: u8) -> MarkerAction {
+    if marker as i8 >= -16 {
+        return MarkerAction::TinyInt(marker as i8);
+    }
+    let high_nibble = marker & 0xF0;
+    if high_nibble == TINY_STRING {
+        return MarkerAction::TinyString(marker & 0x0F);
+    }
+    if high_nibble == TINY_LIST {
+        return MarkerAction::TinyList(marker & 0x0F);
+    }
+    if high_nibble == TINY_MAP {
+        return MarkerAction::TinyMap(marker & 0x0F);
+    }
+    if high_nibble == TINY_STRUCT {
+        return MarkerAction::TinyStruct(marker & 0x0F);
+    }
+    match marker {
+        NULL => MarkerAction::Null,
+        FALSE => MarkerAction::False,
+        TRUE => MarkerAction::True,
+        FLOAT_64 => MarkerAction::Float64,
+        INT_8 => MarkerAction::Int8,
+        INT_16 => MarkerAction::Int16,
+        INT_32 => MarkerAction::Int32,
+        INT_64 => MarkerAction::Int64,
+        BYTES_8 => MarkerAction::Bytes8,
+        BYTES_16 => MarkerAction::Bytes16,
+        BYTES_32 => MarkerAction::Bytes32,
+        STRING_8 => MarkerAction::String8,
+        STRING_16 => MarkerAction::String16,
+        STRING_32 => MarkerAction::String32,
+        LIST_8 => MarkerAction::List8,
+        LIST_16 => MarkerAction::List16,
+        LIST_32 => MarkerAction::List32,
+        MAP_8 => MarkerAction::Map8,
+        MAP_16 => MarkerAction::Map16,
+        MAP_32 => MarkerAction::Map32,
+        _ => MarkerAction::Invalid,
+    }
+}
+
+const fn build_marker_table() -> [MarkerAction; 256] {
+    let mut table = [MarkerAction::Invalid; 256];
+    let mut marker = 0usize;
+    while marker < 256 {
+        table[marker] = classify_marker(marker as u8);
+        marker += 1;
+    }
+    table
+}
+
+/// Maps every possible marker byte to its decode action. Keeps the hot
+/// path (tiny markers, small scalars) a single indexed branch instead of
+/// a `match` with several `_ if` guard arms re-testing the same ranges
+/// for every element of every list/map decoded.
+static MARKER_TABLE: [MarkerAction; 256] = build_marker_table();
+
+/// Default cap on how many lists/maps/structs may nest inside one
+/// another. Generous enough for any real Bolt payload, low enough that
+/// reaching it can't come close to exhausting the stack.
+pub const DEFAULT_MAX_DEPTH: usize = 500;
+
 #[pyfunction]
+#[pyo3(signature = (bytes, idx, hydration_hooks, max_depth = DEFAULT_MAX_DEPTH))]
 pub(super) fn unpack(
     py: Python,
     bytes: &PyByteArray,
     idx: usize,
     hydration_hooks: Option<&PyDict>,
+    max_depth: usize,
 ) -> PyResult<(PyObject, usize)> {
-    let mut decoder = PackStreamDecoder::new(bytes, py, idx, hydration_hooks);
+    let mut decoder = PackStreamDecoder::new(bytes, py, idx, hydration_hooks, max_depth);
     let result = decoder.read()?;
     Ok((result, decoder.index))
 }
 
+/// Like `unpack`, but for a `bytes` buffer that may not yet hold a full
+/// value (e.g. a Bolt chunk that arrived before the rest of the message).
+///
+/// Returns `Some((value, new_idx))` on a complete decode, same as
+/// `unpack`. Returns `None` if the buffer ended in the middle of a value
+/// rather than on a malformed one: the caller should append more bytes
+/// and call again with the *original* `idx`, since a `None` result never
+/// consumes any input. Genuinely malformed input (bad marker, invalid
+/// UTF-8, ...) still raises `PyValueError`, same as `unpack`.
+///
+/// To drain every complete message out of a buffer that may contain
+/// several concatenated ones (or end mid-message), callers loop this
+/// until it returns `None`, feeding `new_idx` back in as `idx`:
+/// `idx = 0; while let Some((value, new_idx)) = unpack_incremental(..., idx, ...)? { idx = new_idx; ... }`.
+///
+/// A `None` result re-reads from `idx` on the next call rather than
+/// resuming past whatever prefix was already validated, so appending to
+/// a value that keeps stalling (e.g. one large chunked string) re-parses
+/// bytes seen in earlier calls instead of only the newly appended ones.
+/// Fine for Bolt's typical chunk sizes; callers built around very large
+/// messages arriving in very small increments should budget for that.
+#[pyfunction]
+#[pyo3(signature = (bytes, idx, hydration_hooks, max_depth = DEFAULT_MAX_DEPTH))]
+pub(super) fn unpack_incremental(
+    py: Python,
+    bytes: &PyByteArray,
+    idx: usize,
+    hydration_hooks: Option<&PyDict>,
+    max_depth: usize,
+) -> PyResult<Option<(PyObject, usize)>> {
+    let mut decoder = PackStreamDecoder::new(bytes, py, idx, hydration_hooks, max_depth);
+    match decoder.read() {
+        Ok(value) => Ok(Some((value, decoder.index))),
+        Err(e) if decoder.eof => {
+            let _ = e;
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
 struct PackStreamDecoder<'a> {
     bytes: &'a PyByteArray,
     py: Python<'a>,
     index: usize,
     hydration_hooks: Option<&'a PyDict>,
+    /// Set when a read ran past the end of `bytes` rather than hitting a
+    /// malformed marker/length. Lets `unpack_incremental` tell "need more
+    /// data" apart from "this input is corrupt" without string-matching
+    /// error messages.
+    eof: bool,
+    /// How many lists/maps/structs we're currently nested inside of.
+    /// Lists are only as expensive to decode as they are wide: entering
+    /// one costs a single increment no matter how many elements it has,
+    /// so wide-but-shallow payloads stay cheap while genuinely deep
+    /// nesting still trips `max_depth`.
+    depth: usize,
+    max_depth: usize,
 }
 
 impl<'a> PackStreamDecoder<'a> {
@@ -50,13 +214,30 @@ impl<'a> PackStreamDecoder<'a> {
         py: Python<'a>,
         idx: usize,
         hydration_hooks: Option<&'a PyDict>,
+        max_depth: usize,
     ) -> Self {
         Self {
             bytes,
             py,
             index: idx,
             hydration_hooks,
+            eof: false,
+            depth: 0,
+            max_depth,
+        }
+    }
+
+    /// Runs `f` with the nesting depth incremented by one, raising
+    /// instead of calling `f` if that would exceed `max_depth`.
+    fn with_nesting<T>(&mut self, f: impl FnOnce(&mut Self) -> PyResult<T>) -> PyResult<T> {
+        self.depth += 1;
+        if self.depth > self.max_depth {
+            self.depth -= 1;
+            return Err(PyErr::new::<PyValueError, _>("PackStream nesting too deep"));
         }
+        let result = f(self);
+        self.depth -= 1;
+        result
     }
 
     fn read(&mut self) -> PyResult<PyObject> {
@@ -65,72 +246,69 @@ impl<'a> PackStreamDecoder<'a> {
     }
 
     fn read_value(&mut self, marker: u8) -> PyResult<PyObject> {
-        let high_nibble = marker & 0xF0;
-
-        Ok(match marker {
-            // tiny int
-            _ if marker as i8 >= -16 => (marker as i8).to_object(self.py),
-            NULL => self.py.None(),
-            FLOAT_64 => self.read_f64()?.to_object(self.py),
-            FALSE => false.to_object(self.py),
-            TRUE => true.to_object(self.py),
-            INT_8 => self.read_i8()?.to_object(self.py),
-            INT_16 => self.read_i16()?.to_object(self.py),
-            INT_32 => self.read_i32()?.to_object(self.py),
-            INT_64 => self.read_i64()?.to_object(self.py),
-            BYTES_8 => {
+        Ok(match MARKER_TABLE[marker as usize] {
+            MarkerAction::TinyInt(v) => v.to_object(self.py),
+            MarkerAction::Null => self.py.None(),
+            MarkerAction::False => false.to_object(self.py),
+            MarkerAction::True => true.to_object(self.py),
+            MarkerAction::Float64 => self.read_f64()?.to_object(self.py),
+            MarkerAction::Int8 => self.read_i8()?.to_object(self.py),
+            MarkerAction::Int16 => self.read_i16()?.to_object(self.py),
+            MarkerAction::Int32 => self.read_i32()?.to_object(self.py),
+            MarkerAction::Int64 => self.read_i64()?.to_object(self.py),
+            MarkerAction::Bytes8 => {
                 let len = self.read_u8()?;
                 self.read_bytes(len)?
             }
-            BYTES_16 => {
+            MarkerAction::Bytes16 => {
                 let len = self.read_u16()?;
                 self.read_bytes(len)?
             }
-            BYTES_32 => {
+            MarkerAction::Bytes32 => {
                 let len = self.read_u32()?;
                 self.read_bytes(len)?
             }
-            _ if high_nibble == TINY_STRING => self.read_string((marker & 0x0F).into())?,
-            STRING_8 => {
+            MarkerAction::TinyString(len) => self.read_string(len.into())?,
+            MarkerAction::String8 => {
                 let len = self.read_u8()?;
                 self.read_string(len)?
             }
-            STRING_16 => {
+            MarkerAction::String16 => {
                 let len = self.read_u16()?;
                 self.read_string(len)?
             }
-            STRING_32 => {
+            MarkerAction::String32 => {
                 let len = self.read_u32()?;
                 self.read_string(len)?
             }
-            _ if high_nibble == TINY_LIST => self.read_list((marker & 0x0F).into())?,
-            LIST_8 => {
+            MarkerAction::TinyList(len) => self.read_list(len.into())?,
+            MarkerAction::List8 => {
                 let len = self.read_u8()?;
                 self.read_list(len)?
             }
-            LIST_16 => {
+            MarkerAction::List16 => {
                 let len = self.read_u16()?;
                 self.read_list(len)?
             }
-            LIST_32 => {
+            MarkerAction::List32 => {
                 let len = self.read_u32()?;
                 self.read_list(len)?
             }
-            _ if high_nibble == TINY_MAP => self.read_map((marker & 0x0F).into())?,
-            MAP_8 => {
+            MarkerAction::TinyMap(len) => self.read_map(len.into())?,
+            MarkerAction::Map8 => {
                 let len = self.read_u8()?;
                 self.read_map(len)?
             }
-            MAP_16 => {
+            MarkerAction::Map16 => {
                 let len = self.read_u16()?;
                 self.read_map(len)?
             }
-            MAP_32 => {
+            MarkerAction::Map32 => {
                 let len = self.read_u32()?;
                 self.read_map(len)?
             }
-            _ if high_nibble == TINY_STRUCT => self.read_struct((marker & 0x0F).into())?,
-            _ => {
+            MarkerAction::TinyStruct(len) => self.read_struct(len.into())?,
+            MarkerAction::Invalid => {
                 // raise ValueError("Unknown PackStream marker %02X" % marker)
                 return Err(PyErr::new::<PyValueError, _>(format!(
                     "Unknown PackStream marker {:02X}",
@@ -144,25 +322,34 @@ impl<'a> PackStreamDecoder<'a> {
         if length == 0 {
             return Ok(PyList::empty(self.py).to_object(self.py));
         }
-        let mut items = Vec::with_capacity(length);
-        for _ in 0..length {
-            items.push(self.read()?);
-        }
-        Ok(items.to_object(self.py))
+        self.with_nesting(|this| {
+            // Every element takes at least one byte, so never reserve more
+            // than the buffer could hold, whatever the length header says.
+            let remaining = this.bytes.len().saturating_sub(this.index);
+            let mut items = Vec::with_capacity(length.min(remaining));
+            for _ in 0..length {
+                items.push(this.read()?);
+            }
+            Ok(items.to_object(this.py))
+        })
     }
 
     fn read_string(&mut self, length: usize) -> PyResult<PyObject> {
         if length == 0 {
             return Ok("".to_object(self.py));
         }
+        let end = self.index + length;
         let data = unsafe {
             // Safety: we're holding the GIL, and don't interact with Python while using the bytes
-            let data = &self.bytes.as_bytes()[self.index..self.index + length];
+            let Some(data) = self.bytes.as_bytes().get(self.index..end) else {
+                self.eof = true;
+                return Err(PyErr::new::<PyValueError, _>("Nothing to unpack"));
+            };
             // We have to copy the data to uphold the safety invariant.
             String::from_utf8(data.into())
                 .map_err(|e| PyErr::new::<PyValueError, _>(e.to_string()))?
         };
-        self.index += length;
+        self.index = end;
         Ok(data.to_object(self.py))
     }
 
@@ -170,48 +357,59 @@ impl<'a> PackStreamDecoder<'a> {
         if length == 0 {
             return Ok(PyDict::new(self.py).to_object(self.py));
         }
-        let mut key_value_pairs: Vec<(PyObject, PyObject)> = Vec::with_capacity(length);
-        for _ in 0..length {
-            let len = self.read_string_length()?;
-            let key = self.read_string(len)?;
-            let value = self.read()?;
-            key_value_pairs.push((key, value));
-        }
-        Ok(key_value_pairs.into_py_dict(self.py).into())
+        self.with_nesting(|this| {
+            let remaining = this.bytes.len().saturating_sub(this.index);
+            let mut key_value_pairs: Vec<(PyObject, PyObject)> =
+                Vec::with_capacity(length.min(remaining));
+            for _ in 0..length {
+                let len = this.read_string_length()?;
+                let key = this.read_string(len)?;
+                let value = this.read()?;
+                key_value_pairs.push((key, value));
+            }
+            Ok(key_value_pairs.into_py_dict(this.py).into())
+        })
     }
 
     fn read_bytes(&mut self, length: usize) -> PyResult<PyObject> {
         if length == 0 {
             return Ok(PyBytes::new(self.py, &[]).to_object(self.py));
         }
+        let end = self.index + length;
         let data = unsafe {
             // Safety: we're holding the GIL, and don't interact with Python while using the bytes.
+            let Some(data) = self.bytes.as_bytes().get(self.index..end) else {
+                self.eof = true;
+                return Err(PyErr::new::<PyValueError, _>("Nothing to unpack"));
+            };
             // We have to copy the data to uphold the safety invariant.
-            self.bytes.as_bytes()[self.index..self.index + length].to_vec()
+            data.to_vec()
         };
-        self.index += length;
+        self.index = end;
         Ok(PyBytes::new(self.py, &data).to_object(self.py))
     }
 
     fn read_struct(&mut self, length: usize) -> PyResult<PyObject> {
         let tag = self.read_byte()?;
-        let mut fields = Vec::with_capacity(length);
-        for _ in 0..length {
-            fields.push(self.read()?)
-        }
-        let mut bolt_struct = Structure { tag, fields }.into_py(self.py);
-        let Some(hooks) = self.hydration_hooks else {
-            return Ok(bolt_struct);
-        };
+        self.with_nesting(|this| {
+            let mut fields = Vec::with_capacity(length);
+            for _ in 0..length {
+                fields.push(this.read()?)
+            }
+            let mut bolt_struct = Structure { tag, fields }.into_py(this.py);
+            let Some(hooks) = this.hydration_hooks else {
+                return Ok(bolt_struct);
+            };
 
-        let attr = bolt_struct.getattr(self.py, intern!(self.py, "__class__"))?;
-        if let Some(res) = hooks.get_item(attr) {
-            bolt_struct = res
-                .call(PyTuple::new(self.py, [bolt_struct]), None)?
-                .into_py(self.py);
-        }
+            let attr = bolt_struct.getattr(this.py, intern!(this.py, "__class__"))?;
+            if let Some(res) = hooks.get_item(attr) {
+                bolt_struct = res
+                    .call(PyTuple::new(this.py, [bolt_struct]), None)?
+                    .into_py(this.py);
+            }
 
-        Ok(bolt_struct)
+            Ok(bolt_struct)
+        })
     }
 
     fn read_string_length(&mut self) -> PyResult<usize> {
@@ -232,11 +430,13 @@ impl<'a> PackStreamDecoder<'a> {
     fn read_byte(&mut self) -> PyResult<u8> {
         let byte = unsafe {
             // Safety: we're holding the GIL, and don't interact with Python while using the bytes
-            *self
-                .bytes
-                .as_bytes()
-                .get(self.index)
-                .ok_or_else(|| PyErr::new::<PyValueError, _>("Nothing to unpack"))?
+            match self.bytes.as_bytes().get(self.index) {
+                Some(b) => *b,
+                None => {
+                    self.eof = true;
+                    return Err(PyErr::new::<PyValueError, _>("Nothing to unpack"));
+                }
+            }
         };
         self.index += 1;
         Ok(byte)
@@ -251,7 +451,10 @@ impl<'a> PackStreamDecoder<'a> {
                     self.index = to;
                     Ok(<[u8; N]>::try_from(b).expect("we know the slice has exactly N values"))
                 }
-                None => Err(PyErr::new::<PyValueError, _>("Nothing to unpack")),
+                None => {
+                    self.eof = true;
+                    Err(PyErr::new::<PyValueError, _>("Nothing to unpack"))
+                }
             }
         }
     }
@@ -294,3 +497,51 @@ impl<'a> PackStreamDecoder<'a> {
         self.read_n_bytes().map(f64::from_be_bytes)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nesting_past_max_depth_is_rejected() {
+        Python::with_gil(|py| {
+            // `DEFAULT_MAX_DEPTH` nested TINY_LISTs (each holding exactly
+            // the next one) is the deepest this is allowed to go; one more
+            // level must be rejected before it ever touches the stack.
+            let mut data = vec![0x91u8 /* TINY_LIST, 1 element */; DEFAULT_MAX_DEPTH + 1];
+            data.push(0x00); // innermost element: tiny int 0, never reached
+
+            let buffer = PyByteArray::new(py, &data);
+            let err = unpack(py, buffer, 0, None, DEFAULT_MAX_DEPTH)
+                .expect_err("buffer nests one level past max_depth");
+            assert!(
+                err.to_string().contains("PackStream nesting too deep"),
+                "unexpected error: {err}"
+            );
+        });
+    }
+
+    #[test]
+    fn wide_shallow_list_decodes_fine() {
+        Python::with_gil(|py| {
+            // A single, very wide list is only one level of nesting no
+            // matter how many elements it has, so it must decode fine
+            // well under `DEFAULT_MAX_DEPTH`.
+            const LEN: usize = 10_000;
+            let mut data = vec![LIST_32];
+            data.extend_from_slice(&(LEN as u32).to_be_bytes());
+            data.extend(std::iter::repeat(0x01u8 /* tiny int 1 */).take(LEN));
+
+            let buffer = PyByteArray::new(py, &data);
+            let (value, idx) = unpack(py, buffer, 0, None, DEFAULT_MAX_DEPTH)
+                .expect("a wide-but-shallow list must not trip the depth guard");
+            assert_eq!(idx, data.len());
+
+            let list = value
+                .as_ref(py)
+                .downcast::<PyList>()
+                .expect("decoded value is a list");
+            assert_eq!(list.len(), LEN);
+        });
+    }
+}